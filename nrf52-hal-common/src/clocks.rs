@@ -0,0 +1,85 @@
+//! HAL interface for the CLOCK peripheral
+//!
+//! This configures and starts the low-frequency clock (LFCLK) that the
+//! [`crate::rtc`] module requires to be running before an RTC is started.
+//! The high-frequency clock is not configured here.
+
+use core::marker::PhantomData;
+
+use crate::target::CLOCK;
+
+/// LFCLK has not been started yet
+pub struct LfOscStopped;
+/// LFCLK has been started and `EVENTS_LFCLKSTARTED` has fired
+pub struct LfOscStarted;
+
+/// Selectable sources for the low-frequency clock (LFCLK), mirroring the
+/// options of the `LFCLKSRC` register field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LfclkSource {
+    InternalRC,
+    Synthesized,
+    ExternalXtal,
+    ExternalLowSwing,
+    ExternalFullSwing,
+}
+
+/// An extension trait for constructing the high level interface
+pub trait ClocksExt {
+    fn constrain(self) -> Clocks<LfOscStopped>;
+}
+
+impl ClocksExt for CLOCK {
+    fn constrain(self) -> Clocks<LfOscStopped> {
+        Clocks {
+            periph: self,
+            _lfclk: PhantomData,
+        }
+    }
+}
+
+/// An opaque high level interface to the CLOCK peripheral, tracking in its
+/// type whether LFCLK has been started
+pub struct Clocks<L> {
+    periph: CLOCK,
+    _lfclk: PhantomData<L>,
+}
+
+impl<L> Clocks<L> {
+    /// Select the clock source that will drive LFCLK. Must be called before
+    /// [`Clocks::start_lfclk`]; calling it again before starting changes the
+    /// pending source.
+    pub fn set_lfclk_source(self, src: LfclkSource) -> Self {
+        self.periph.lfclksrc.write(|w| match src {
+            LfclkSource::InternalRC => w.src().rc(),
+            LfclkSource::Synthesized => w.src().synth(),
+            LfclkSource::ExternalXtal => w.src().xtal(),
+            LfclkSource::ExternalLowSwing => w.src().xtal_low_swing(),
+            LfclkSource::ExternalFullSwing => w.src().xtal_full_swing(),
+        });
+        self
+    }
+}
+
+impl Clocks<LfOscStopped> {
+    /// Start LFCLK using the previously selected source and block until
+    /// `EVENTS_LFCLKSTARTED` is signalled
+    pub fn start_lfclk(self) -> Clocks<LfOscStarted> {
+        unsafe { self.periph.tasks_lfclkstart.write(|w| w.bits(1)) };
+
+        while self.periph.events_lfclkstarted.read().bits() == 0 {}
+        unsafe { self.periph.events_lfclkstarted.write(|w| w.bits(0)) };
+
+        Clocks {
+            periph: self.periph,
+            _lfclk: PhantomData,
+        }
+    }
+}
+
+impl Clocks<LfOscStarted> {
+    /// Destructure the high level interface. Does not stop LFCLK.
+    pub fn free(self) -> CLOCK {
+        self.periph
+    }
+}
@@ -0,0 +1,170 @@
+//! A software calendar layered over [`crate::rtc::Rtc`]
+//!
+//! The nRF RTC peripheral has no calendar hardware of its own, just a tick
+//! counter. [`Calendar`] turns that counter into civil date/time by storing a
+//! base Unix timestamp and converting elapsed ticks to elapsed seconds using
+//! the configured `fRTC`, then converting Unix seconds to a date with the
+//! well-known days-from-civil algorithm.
+
+use crate::rtc::{Rtc, RtcExt, Started};
+
+/// A civil date/time, with one second resolution
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NaiveDateTime {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+/// Days since the Unix epoch for the given civil date, using Howard
+/// Hinnant's `days_from_civil` algorithm (shifts the year so March is month 0,
+/// then buckets days into 400/100/4 year eras to account for leap years)
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400; // [0, 399]
+    let mp = (m as i64 + 9) % 12; // [0, 11], Mar = 0 .. Feb = 11
+    let doy = (153 * mp + 2) / 5 + d as i64 - 1; // [0, 365]
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy; // [0, 146096]
+    era * 146097 + doe - 719468
+}
+
+/// Inverse of [`days_from_civil`]
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097; // [0, 146096]
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365; // [0, 399]
+    let y = yoe + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // [0, 365]
+    let mp = (5 * doy + 2) / 153; // [0, 11]
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32; // [1, 31]
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32; // [1, 12]
+    (if m <= 2 { y + 1 } else { y }, m, d)
+}
+
+fn datetime_to_unix(dt: &NaiveDateTime) -> i64 {
+    let days = days_from_civil(dt.year as i64, dt.month as u32, dt.day as u32);
+    days * 86_400 + dt.hour as i64 * 3600 + dt.minute as i64 * 60 + dt.second as i64
+}
+
+fn unix_to_datetime(secs: i64) -> NaiveDateTime {
+    let days = secs.div_euclid(86_400);
+    let secs_of_day = secs.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    NaiveDateTime {
+        year: year as i32,
+        month: month as u8,
+        day: day as u8,
+        hour: (secs_of_day / 3600) as u8,
+        minute: ((secs_of_day % 3600) / 60) as u8,
+        second: (secs_of_day % 60) as u8,
+    }
+}
+
+/// A software calendar built on top of an [`Rtc`]'s 64 bit extended tick
+/// counter, giving wall-clock timestamps without an external RTC chip.
+///
+/// Sub-second accuracy depends on `fRTC` (`32_768 / (prescaler + 1)`):
+/// elapsed ticks are converted to whole elapsed seconds, so resolution is
+/// limited by the configured prescaler.
+pub struct Calendar<T> {
+    rtc: Rtc<T, Started>,
+    f_rtc: u32,
+    base_ticks: u64,
+    base_unix_secs: i64,
+}
+
+impl<T> Calendar<T>
+where
+    T: RtcExt,
+{
+    /// Wrap `rtc`, treating `datetime` as "now". The tick rate is read back
+    /// from the RTC's own `PRESCALER` register, so it always matches the
+    /// prescaler the RTC was actually configured with.
+    pub fn new(rtc: Rtc<T, Started>, datetime: NaiveDateTime) -> Self {
+        let f_rtc = rtc.get_frequency();
+        let base_ticks = rtc.get_counter_64();
+        Calendar {
+            rtc,
+            f_rtc,
+            base_ticks,
+            base_unix_secs: datetime_to_unix(&datetime),
+        }
+    }
+
+    /// Re-anchor the calendar to `datetime`, as of the current tick count
+    pub fn set_datetime(&mut self, datetime: NaiveDateTime) {
+        self.base_ticks = self.rtc.get_counter_64();
+        self.base_unix_secs = datetime_to_unix(&datetime);
+    }
+
+    /// Read back the current date/time
+    pub fn datetime(&self) -> NaiveDateTime {
+        let elapsed_ticks = self.rtc.get_counter_64() - self.base_ticks;
+        let elapsed_secs = (elapsed_ticks / self.f_rtc as u64) as i64;
+        unix_to_datetime(self.base_unix_secs + elapsed_secs)
+    }
+
+    /// Destructure, returning the underlying RTC
+    pub fn release(self) -> Rtc<T, Started> {
+        self.rtc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dt(year: i32, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> NaiveDateTime {
+        NaiveDateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        }
+    }
+
+    #[test]
+    fn epoch_round_trips() {
+        let epoch = dt(1970, 1, 1, 0, 0, 0);
+        assert_eq!(datetime_to_unix(&epoch), 0);
+        assert_eq!(unix_to_datetime(0), epoch);
+    }
+
+    #[test]
+    fn leap_day_feb_29_round_trips() {
+        // 2024 is divisible by 4 but not by 100, so it is a leap year
+        let leap_day = dt(2024, 2, 29, 12, 0, 0);
+        assert_eq!(unix_to_datetime(datetime_to_unix(&leap_day)), leap_day);
+    }
+
+    #[test]
+    fn century_non_leap_year_has_no_feb_29() {
+        // 1900 is divisible by 100 but not by 400, so it is *not* a leap year;
+        // Feb 28 + 1 day should roll over straight to Mar 1
+        let feb_28 = dt(1900, 2, 28, 23, 59, 59);
+        let one_second_later = unix_to_datetime(datetime_to_unix(&feb_28) + 1);
+        assert_eq!(one_second_later, dt(1900, 3, 1, 0, 0, 0));
+    }
+
+    #[test]
+    fn quadricentennial_leap_year_round_trips() {
+        // 2000 is divisible by 400, so it *is* a leap year despite also being
+        // divisible by 100
+        let leap_day = dt(2000, 2, 29, 23, 59, 59);
+        assert_eq!(unix_to_datetime(datetime_to_unix(&leap_day)), leap_day);
+    }
+
+    #[test]
+    fn arbitrary_datetime_round_trips() {
+        let now = dt(2026, 7, 30, 13, 45, 12);
+        assert_eq!(unix_to_datetime(datetime_to_unix(&now)), now);
+    }
+}
@@ -1,12 +1,25 @@
 //! A high level interface for RTC peripherals
 
+use core::cell::UnsafeCell;
+use core::future::Future;
 use core::ops::Deref;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
+use core::task::{Context, Poll, Waker};
 
+use embedded_hal::blocking::delay::{DelayMs, DelayUs};
+use embedded_hal::timer::{CountDown, Periodic};
+use void::Void;
+
+use crate::clocks::{Clocks, LfOscStarted};
 use crate::target::{rtc0, Interrupt, NVIC, RTC0, RTC1};
 
 #[cfg(not(feature = "52810"))]
 use crate::target::RTC2;
 
+/// Number of independent compare channels available on every RTCn instance
+const COMPARE_CHANNELS: usize = 4;
+
 // Zero Size Type State structs
 
 /// The RTC has been stopped
@@ -23,11 +36,49 @@ pub struct Rtc<T, M> {
 /// An extension trait for constructing the high level interface
 pub trait RtcExt : Deref<Target=rtc0::RegisterBlock> + Sized {
     fn constrain(self) -> Rtc<Self, Stopped>;
+
+    /// Per-instance wakers used to resume tasks awaiting [`Rtc::delay`],
+    /// indexed by [`RtcCompareReg`]
+    #[doc(hidden)]
+    fn wakers() -> &'static [AtomicWaker; COMPARE_CHANNELS];
+
+    /// Per-instance, per-channel completion flags backing [`Rtc::delay`].
+    ///
+    /// `events_compare` is cleared by [`Rtc::on_interrupt`] before the waker
+    /// is woken, so the pending future cannot rely on the hardware event bit
+    /// still being set once it is polled again; these flags carry that
+    /// "did it fire" signal across instead.
+    #[doc(hidden)]
+    fn fired() -> &'static [AtomicBool; COMPARE_CHANNELS];
+
+    /// Per-instance software overflow count backing [`Rtc::get_counter_64`]
+    #[doc(hidden)]
+    fn overflow_count() -> &'static AtomicU32;
+
+    /// The NVIC interrupt line this instance is wired to, used by
+    /// [`Rtc::unmask_interrupt`] and friends
+    const INTERRUPT: Interrupt;
 }
 
 macro_rules! impl_rtc_ext {
-    ($($rtc:ty,)*) => {
+    ($($rtc:ty => $wakers:ident, $fired:ident, $overflow:ident, $interrupt:expr,)*) => {
         $(
+            static $wakers: [AtomicWaker; COMPARE_CHANNELS] = [
+                AtomicWaker::new(),
+                AtomicWaker::new(),
+                AtomicWaker::new(),
+                AtomicWaker::new(),
+            ];
+
+            static $fired: [AtomicBool; COMPARE_CHANNELS] = [
+                AtomicBool::new(false),
+                AtomicBool::new(false),
+                AtomicBool::new(false),
+                AtomicBool::new(false),
+            ];
+
+            static $overflow: AtomicU32 = AtomicU32::new(0);
+
             impl RtcExt for $rtc {
                 fn constrain(self) -> Rtc<$rtc, Stopped> {
                     Rtc {
@@ -35,15 +86,32 @@ macro_rules! impl_rtc_ext {
                         _mode: Stopped,
                     }
                 }
+
+                fn wakers() -> &'static [AtomicWaker; COMPARE_CHANNELS] {
+                    &$wakers
+                }
+
+                fn fired() -> &'static [AtomicBool; COMPARE_CHANNELS] {
+                    &$fired
+                }
+
+                fn overflow_count() -> &'static AtomicU32 {
+                    &$overflow
+                }
+
+                const INTERRUPT: Interrupt = $interrupt;
             }
         )*
     }
 }
 
-impl_rtc_ext!(RTC0, RTC1,);
+impl_rtc_ext!(
+    RTC0 => RTC0_WAKERS, RTC0_FIRED, RTC0_OVERFLOW, Interrupt::RTC0,
+    RTC1 => RTC1_WAKERS, RTC1_FIRED, RTC1_OVERFLOW, Interrupt::RTC1,
+);
 
 #[cfg(not(feature = "52810"))]
-impl_rtc_ext!(RTC2,);
+impl_rtc_ext!(RTC2 => RTC2_WAKERS, RTC2_FIRED, RTC2_OVERFLOW, Interrupt::RTC2,);
 
 /// Interrupts/Events that can be generated by the RTCn peripheral
 pub enum RtcInterrupt {
@@ -56,6 +124,7 @@ pub enum RtcInterrupt {
 }
 
 /// Compare registers available on the RTCn
+#[derive(Clone, Copy)]
 pub enum RtcCompareReg {
     Compare0,
     Compare1,
@@ -63,21 +132,140 @@ pub enum RtcCompareReg {
     Compare3,
 }
 
-impl<T, M> Rtc<T, M>
+impl RtcCompareReg {
+    fn index(self) -> usize {
+        match self {
+            RtcCompareReg::Compare0 => 0,
+            RtcCompareReg::Compare1 => 1,
+            RtcCompareReg::Compare2 => 2,
+            RtcCompareReg::Compare3 => 3,
+        }
+    }
+}
+
+/// A single-slot waker cell that can be safely shared between an interrupt
+/// handler and the async task(s) polling on it, modelled after the classic
+/// `futures` `AtomicWaker`.
+pub struct AtomicWaker {
+    state: AtomicU8,
+    waker: UnsafeCell<Option<Waker>>,
+}
+
+const WA_WAITING: u8 = 0;
+const WA_REGISTERING: u8 = 1;
+const WA_WAKING: u8 = 2;
+
+// SAFETY: access to `waker` is guarded by `state`, which is only ever
+// touched through the atomic operations below.
+unsafe impl Sync for AtomicWaker {}
+
+impl AtomicWaker {
+    const fn new() -> Self {
+        AtomicWaker {
+            state: AtomicU8::new(WA_WAITING),
+            waker: UnsafeCell::new(None),
+        }
+    }
+
+    /// Record `waker` as the one to notify on the next call to `wake`
+    fn register(&self, waker: &Waker) {
+        match self
+            .state
+            .compare_exchange(WA_WAITING, WA_REGISTERING, Ordering::Acquire, Ordering::Acquire)
+        {
+            Ok(_) => {
+                unsafe { *self.waker.get() = Some(waker.clone()) };
+                if self
+                    .state
+                    .compare_exchange(WA_REGISTERING, WA_WAITING, Ordering::AcqRel, Ordering::Acquire)
+                    .is_err()
+                {
+                    // a `wake` was attempted while we were registering; the waker we
+                    // just stored is responsible for waking the task ourselves
+                    let waker = unsafe { (*self.waker.get()).take() };
+                    self.state.store(WA_WAITING, Ordering::Release);
+                    if let Some(waker) = waker {
+                        waker.wake();
+                    }
+                }
+            }
+            Err(WA_WAKING) => waker.wake_by_ref(),
+            Err(_) => {}
+        }
+    }
+
+    /// Wake the task previously registered, if any
+    fn wake(&self) {
+        if self.state.swap(WA_WAKING, Ordering::AcqRel) == WA_WAITING {
+            let waker = unsafe { (*self.waker.get()).take() };
+            self.state.store(WA_WAITING, Ordering::Release);
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// A future returned by [`Rtc::delay`] that resolves once `ticks` RTC ticks
+/// have elapsed, as signalled by the associated compare channel firing
+pub struct RtcDelay<'a, T> {
+    rtc: &'a Rtc<T, Started>,
+    channel: RtcCompareReg,
+    ticks: u32,
+    armed: bool,
+}
+
+impl<'a, T> Future for RtcDelay<'a, T>
 where
     T: RtcExt,
 {
-    /// Enable/start the Real Time Counter
-    pub fn enable_counter(self) -> Rtc<T, Started> {
-        unsafe {
-            self.periph.tasks_start.write(|w| w.bits(1));
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        let idx = this.channel.index();
+
+        // register first so that a compare event firing between the register
+        // call and the check below is not missed
+        T::wakers()[idx].register(cx.waker());
+
+        if !this.armed {
+            T::fired()[idx].store(false, Ordering::Release);
+
+            // at least 1 tick, so a zero-length delay still waits for the
+            // next compare match instead of aliasing the current counter value
+            let ticks = this.ticks.max(1);
+            let target = this.rtc.get_counter().wrapping_add(ticks) & 0x00FF_FFFF;
+            unsafe {
+                this.rtc.periph.cc[idx].write(|w| w.bits(target));
+                // drop any stale match from an earlier `cc` value, so enabling
+                // the interrupt below does not complete the delay at once
+                this.rtc.periph.events_compare[idx].write(|w| w.bits(0));
+            }
+            match this.channel {
+                RtcCompareReg::Compare0 => this.rtc.periph.intenset.write(|w| w.compare0().set()),
+                RtcCompareReg::Compare1 => this.rtc.periph.intenset.write(|w| w.compare1().set()),
+                RtcCompareReg::Compare2 => this.rtc.periph.intenset.write(|w| w.compare2().set()),
+                RtcCompareReg::Compare3 => this.rtc.periph.intenset.write(|w| w.compare3().set()),
+            };
+            this.armed = true;
         }
-        Rtc {
-            periph: self.periph,
-            _mode: Started,
+
+        // `events_compare` itself is cleared by `Rtc::on_interrupt` before it
+        // wakes us, so completion is tracked through `fired` rather than the
+        // hardware event bit
+        if T::fired()[idx].load(Ordering::Acquire) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
         }
     }
+}
 
+impl<T, M> Rtc<T, M>
+where
+    T: RtcExt,
+{
     /// Disable/stop the Real Time Counter
     pub fn disable_counter(self) -> Rtc<T, Stopped> {
         unsafe {
@@ -89,6 +277,27 @@ where
         }
     }
 
+    /// Unmask this instance's interrupt line at the NVIC, so interrupts
+    /// enabled with [`Rtc::enable_interrupt`] actually reach the CPU
+    pub fn unmask_interrupt(&mut self) {
+        unsafe { NVIC::unmask(T::INTERRUPT) };
+    }
+
+    /// Mask this instance's interrupt line at the NVIC
+    pub fn mask_interrupt(&mut self) {
+        NVIC::mask(T::INTERRUPT);
+    }
+
+    /// Set this instance's interrupt as pending at the NVIC
+    pub fn pend_interrupt(&mut self) {
+        NVIC::pend(T::INTERRUPT);
+    }
+
+    /// Clear this instance's pending interrupt at the NVIC
+    pub fn unpend_interrupt(&mut self) {
+        NVIC::unpend(T::INTERRUPT);
+    }
+
     /// Enable the generation of a hardware interrupt from a given stimulus
     pub fn enable_interrupt(&mut self, int: RtcInterrupt) {
         match int {
@@ -198,6 +407,12 @@ where
         self.periph.counter.read().bits()
     }
 
+    /// Obtain the currently configured tick rate in Hz, derived from the
+    /// `PRESCALER` register: `fRTC = 32_768 / (prescaler + 1)`
+    pub fn get_frequency(&self) -> u32 {
+        32_768 / (self.periph.prescaler.read().bits() + 1)
+    }
+
     /// Destructure the high level interface. Does not reset any configuration made
     /// to the given RTC peripheral
     pub fn release(self) -> T {
@@ -227,4 +442,210 @@ where
 
         Ok(())
     }
+
+    /// Enable/start the Real Time Counter, taking `_lfclk` as compile-time
+    /// proof that the low-frequency clock has already been started.
+    ///
+    /// This is the only way to start an RTC: the RTC only ticks while LFCLK
+    /// is running, so starting one without a configured LFCLK is a type
+    /// error instead of a silently hanging RTC.
+    pub fn enable_counter(self, _lfclk: &Clocks<LfOscStarted>) -> Rtc<T, Started> {
+        unsafe {
+            self.periph.tasks_start.write(|w| w.bits(1));
+        }
+        Rtc {
+            periph: self.periph,
+            _mode: Started,
+        }
+    }
+}
+
+impl<T> Rtc<T, Started>
+where
+    T: RtcExt,
+{
+    /// Asynchronously wait for `ticks` RTC ticks to elapse, using `channel` as
+    /// a scratch compare register.
+    ///
+    /// The caller is responsible for making sure nothing else is using
+    /// `channel` concurrently, and for wiring [`Rtc::on_interrupt`] into the
+    /// RTCn interrupt handler so the returned future can make progress.
+    pub fn delay(&self, ticks: u32, channel: RtcCompareReg) -> RtcDelay<'_, T> {
+        RtcDelay {
+            rtc: self,
+            channel,
+            ticks,
+            armed: false,
+        }
+    }
+
+    /// Interrupt handler entry point for async support.
+    ///
+    /// Call this from the RTCn interrupt handler: it scans `events_compare`
+    /// for triggered events, clears them, and wakes any task waiting on the
+    /// corresponding [`Rtc::delay`] future.
+    ///
+    /// This only services `events_compare`. If [`Rtc::get_counter_64`] is
+    /// also in use, [`Rtc::on_overflow`] must be called from the same
+    /// interrupt handler to service `events_ovrflw` as well.
+    pub fn on_interrupt(&mut self) {
+        for idx in 0..COMPARE_CHANNELS {
+            if self.periph.events_compare[idx].read().bits() == 1 {
+                unsafe { self.periph.events_compare[idx].write(|w| w.bits(0)) };
+                T::fired()[idx].store(true, Ordering::Release);
+                T::wakers()[idx].wake();
+            }
+        }
+    }
+
+    /// Obtain an extended 64 bit tick count, built from the 24 bit hardware
+    /// `COUNTER` plus a software overflow count maintained by [`Rtc::on_overflow`].
+    ///
+    /// The overflow count is read before and after the hardware counter, and
+    /// the counter is re-read if it changed in between. An overflow that has
+    /// happened but not yet been serviced by [`Rtc::on_overflow`] is counted
+    /// when `events_ovrflw` is still set and the counter reads in the lower
+    /// half of its range.
+    pub fn get_counter_64(&self) -> u64 {
+        let ovf_before = T::overflow_count().load(Ordering::Acquire);
+        let mut counter = self.periph.counter.read().bits();
+        let ovf_after = T::overflow_count().load(Ordering::Acquire);
+
+        let overflow = if ovf_after != ovf_before {
+            counter = self.periph.counter.read().bits();
+            ovf_after
+        } else {
+            ovf_before
+        };
+
+        // an overflow event may already be pending even though our ISR hasn't
+        // run yet; catch that case by looking at the raw counter value
+        let overflow = if counter < (1 << 23) && self.periph.events_ovrflw.read().bits() == 1 {
+            overflow.wrapping_add(1)
+        } else {
+            overflow
+        };
+
+        ((overflow as u64) << 24) | (counter as u64)
+    }
+
+    /// Overflow interrupt handler entry point for [`Rtc::get_counter_64`].
+    ///
+    /// Call this from the RTCn interrupt handler alongside [`Rtc::on_interrupt`]
+    /// to keep the software overflow count in sync with the hardware `COUNTER`.
+    pub fn on_overflow(&mut self) {
+        if self.periph.events_ovrflw.read().bits() == 1 {
+            unsafe { self.periph.events_ovrflw.write(|w| w.bits(0)) };
+            T::overflow_count().fetch_add(1, Ordering::AcqRel);
+        }
+    }
+}
+
+/// An [`embedded_hal::timer::CountDown`]/[`Periodic`] adapter dedicating a
+/// single RTC compare channel to timekeeping, so an [`Rtc`] can be dropped
+/// into any generic driver written against `embedded-hal` instead of
+/// requiring a TIMER peripheral.
+pub struct RtcTimer<T> {
+    rtc: Rtc<T, Started>,
+    channel: RtcCompareReg,
+    f_rtc: u32,
+    period: u32,
+}
+
+impl<T> RtcTimer<T>
+where
+    T: RtcExt,
+{
+    /// Wrap `rtc`, dedicating `channel` to this timer. The tick rate used to
+    /// convert milli-/microseconds to ticks is read back from the RTC's own
+    /// `PRESCALER` register, so it always matches the prescaler the RTC was
+    /// actually configured with. The caller must not use `channel` for
+    /// anything else concurrently.
+    pub fn new(rtc: Rtc<T, Started>, channel: RtcCompareReg) -> Self {
+        let f_rtc = rtc.get_frequency();
+        RtcTimer {
+            rtc,
+            channel,
+            f_rtc,
+            period: 0,
+        }
+    }
+
+    /// Destructure, returning the underlying RTC
+    pub fn release(self) -> Rtc<T, Started> {
+        self.rtc
+    }
+}
+
+impl<T> CountDown for RtcTimer<T>
+where
+    T: RtcExt,
+{
+    type Time = u32;
+
+    fn start<Ticks>(&mut self, count: Ticks)
+    where
+        Ticks: Into<u32>,
+    {
+        let idx = self.channel.index();
+        let counter = self.rtc.periph.counter.read().bits();
+        // at least 1 tick, so a zero-length count-down still waits for the
+        // next compare match instead of aliasing the current counter value
+        let ticks = count.into().max(1);
+        // the compare register is 24 bits wide, so this wraps exactly like
+        // the hardware counter does
+        let target = counter.wrapping_add(ticks) & 0x00FF_FFFF;
+
+        self.period = ticks;
+        T::fired()[idx].store(false, Ordering::Release);
+        unsafe {
+            self.rtc.periph.cc[idx].write(|w| w.bits(target));
+            self.rtc.periph.events_compare[idx].write(|w| w.bits(0));
+        }
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Void> {
+        let idx = self.channel.index();
+
+        // `Rtc::on_interrupt` may run for an entirely different channel's
+        // compare match and, in doing so, clear and service *this* channel's
+        // event too if it happened to also be set. Reading the raw event bit
+        // here would race with that, so completion is additionally tracked
+        // through the shared `fired` flag it maintains, same as `RtcDelay`.
+        let raw_fired = self.rtc.periph.events_compare[idx].read().bits() == 1;
+        let flagged = T::fired()[idx].swap(false, Ordering::AcqRel);
+
+        if raw_fired || flagged {
+            unsafe { self.rtc.periph.events_compare[idx].write(|w| w.bits(0)) };
+            // re-arm `cc` for the next period so the channel keeps firing on
+            // its own, as the `Periodic` impl below promises
+            let next = self.rtc.periph.cc[idx].read().bits().wrapping_add(self.period) & 0x00FF_FFFF;
+            unsafe { self.rtc.periph.cc[idx].write(|w| w.bits(next)) };
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl<T> Periodic for RtcTimer<T> where T: RtcExt {}
+
+impl<T> DelayUs<u32> for RtcTimer<T>
+where
+    T: RtcExt,
+{
+    fn delay_us(&mut self, us: u32) {
+        let ticks = ((us as u64 * self.f_rtc as u64) / 1_000_000).max(1) as u32;
+        self.start(ticks);
+        nb::block!(self.wait()).unwrap();
+    }
+}
+
+impl<T> DelayMs<u32> for RtcTimer<T>
+where
+    T: RtcExt,
+{
+    fn delay_ms(&mut self, ms: u32) {
+        self.delay_us(ms.saturating_mul(1_000));
+    }
 }